@@ -1,37 +1,185 @@
 extern crate proc_macro;
 
-/// Create Byteparse implementation for given structure.
-#[proc_macro_derive(Byteparse)]
+/// Render a field type as the path/array syntax needed to spell out
+/// `<Ty as ::byteparse::Byteparse>::SIZE`. Covers everything PE
+/// structures actually use as field types: plain type paths (`u32`,
+/// `pe::ImageDataDirectory`) and fixed-size arrays of those.
+fn type_to_string(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(p) => {
+            p.path.segments.iter()
+                .map(|s| s.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::")
+        }
+        syn::Type::Array(a) => {
+            let elem = type_to_string(&a.elem);
+
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(n), ..
+            }) = &a.len {
+                format!("[{}; {}]", elem, n.base10_digits())
+            } else {
+                panic!("Byteparse derive only supports array lengths \
+                    given as integer literals.");
+            }
+        }
+        _ => panic!("Byteparse derive does not support this field type."),
+    }
+}
+
+/// What a field's `#[byteparse(...)]` attribute asks for, if anything.
+enum FieldAttr {
+    /// No attribute: field follows right after the previous one.
+    Plain,
+    /// `#[byteparse(skip = N)]`: discard N bytes before the field.
+    Skip(String),
+    /// `#[byteparse(offset = N)]`: pad up to N bytes from struct start.
+    Offset(String)
+}
+
+/// Find a `#[byteparse(...)]` attribute and return its raw inner text,
+/// e.g. `#[byteparse(skip = 4)]` -> `"skip = 4"`.
+fn byteparse_attr_text(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter()
+        .find(|a| a.path.is_ident("byteparse"))
+        .map(|a| {
+            a.tokens.to_string()
+                .trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .trim()
+                .to_string()
+        })
+}
+
+fn field_attr(attrs: &[syn::Attribute]) -> FieldAttr {
+    let text = match byteparse_attr_text(attrs) {
+        Some(t) => t,
+        None => return FieldAttr::Plain
+    };
+
+    if let Some(rest) = text.strip_prefix("skip") {
+        let n = rest.trim_start_matches(|c: char| c == '=' || c.is_whitespace());
+        return FieldAttr::Skip(n.trim().to_string());
+    }
+
+    if let Some(rest) = text.strip_prefix("offset") {
+        let n = rest.trim_start_matches(|c: char| c == '=' || c.is_whitespace());
+        return FieldAttr::Offset(n.trim().to_string());
+    }
+
+    panic!("Unrecognized #[byteparse(...)] field attribute: {}", text);
+}
+
+/// A struct is alignment-padded only when marked `#[byteparse(aligned)]`.
+/// Everything else (no attribute, or the explicit `#[byteparse(packed)]`
+/// acknowledgment) keeps today's packed, gap-free layout.
+fn struct_is_aligned(attrs: &[syn::Attribute]) -> bool {
+    match byteparse_attr_text(attrs).as_deref() {
+        Some("aligned")      => true,
+        Some("packed") | None => false,
+        Some(other) => panic!(
+            "Unrecognized #[byteparse(...)] struct attribute: {}", other)
+    }
+}
+
+/// Create Byteparse and Bytewrite implementations for given structure.
+#[proc_macro_derive(Byteparse, attributes(byteparse))]
 pub fn byteparse(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let input   = syn::parse_macro_input!(input as syn::DeriveInput);
+    let aligned = struct_is_aligned(&input.attrs);
 
     if let syn::Data::Struct(syn::DataStruct { fields, .. }) = &input.data {
-        // Get name of all struct members.
-        let members = fields.iter()
+        // Get name, type and byteparse attribute of all struct members.
+        let members: Vec<(String, String, FieldAttr)> = fields.iter()
             .enumerate()
             .map(|(i, f)| {
                 // In named structs we use names. In tuple structs
                 // we use indices.
-                if let Some(ident) = &f.ident {
+                let name = if let Some(ident) = &f.ident {
                     ident.to_string()
                 } else {
                     format!("{}", i)
-                }
-            });
+                };
+
+                (name, type_to_string(&f.ty), field_attr(&f.attrs))
+            })
+            .collect();
+
+        // How many bytes (if any) to pad/discard before a field,
+        // expressed against a running `__off` byte counter. Shared
+        // between the SIZE calculation and parse_to/write_to so all
+        // three always agree on the struct's actual layout.
+        let pad_expr = |attr: &FieldAttr, ty: &str| -> Option<String> {
+            match attr {
+                FieldAttr::Skip(n)   => Some(format!("({})", n)),
+                FieldAttr::Offset(n) => Some(format!("(({}) - __off)", n)),
+                FieldAttr::Plain if aligned => Some(format!(
+                    "({{ let __a = ::std::mem::align_of::<{}>(); \
+                        (__a - (__off % __a)) % __a }})", ty)),
+                FieldAttr::Plain => None
+            }
+        };
+
+        let mut size_body  = String::from("{ let mut __off: usize = 0;");
+        let mut parse_body = String::from("let mut __off: usize = 0;");
+        let mut write_body = String::from("let mut __off: usize = 0;");
+
+        for (m, ty, attr) in &members {
+            if let Some(pad) = pad_expr(attr, ty) {
+                size_body += &format!("__off += {pad};", pad = pad);
+
+                parse_body += &format!(
+                    "for _ in 0..{pad} {{ \
+                        let mut __b: u8 = 0; \
+                        ::byteparse::Byteparse::parse_to(&mut __b, r)?; \
+                    }} __off += {pad};", pad = pad);
+
+                write_body += &format!(
+                    "for _ in 0..{pad} {{ \
+                        ::byteparse::Bytewrite::write_to(&0u8, w)?; \
+                    }} __off += {pad};", pad = pad);
+            }
+
+            size_body += &format!(
+                "__off += <{ty} as ::byteparse::Byteparse>::SIZE;", ty = ty);
+
+            parse_body += &format!(
+                "::byteparse::Byteparse::parse_to(&mut self.{m}, r)?; \
+                    __off += <{ty} as ::byteparse::Byteparse>::SIZE;",
+                m = m, ty = ty);
+
+            write_body += &format!(
+                "::byteparse::Bytewrite::write_to(&self.{m}, w)?; \
+                    __off += <{ty} as ::byteparse::Byteparse>::SIZE;",
+                m = m, ty = ty);
+        }
+
+        size_body += "__off }";
+        parse_body += "let _ = __off;";
+        write_body += "let _ = __off;";
 
         // Implement Byteparse trait for given struct.
         let mut o = format!(
             "unsafe impl ::byteparse::Byteparse for {} {{", input.ident);
+
+        o += &format!("const SIZE: usize = {};", size_body);
+
         o += "fn parse_to<R>(&mut self, r: &mut R) -> ::std::io::Result<()>";
         o += "    where R: ::std::io::Read {";
-        
-        // Parse every member individually.
-        for m in members {
-            o += &format!(
-                "::byteparse::Byteparse::parse_to(&mut self.{}, r)?;", m);
-        }
+        o += &parse_body;
+        o += "::std::result::Result::Ok(())";
+        o += "}}";
 
-        // Return success and close function.
+        // Implement Bytewrite trait for the same struct, writing
+        // members (and any skip/offset/alignment padding) back out
+        // in the same order they were parsed in.
+        o += &format!(
+            "impl ::byteparse::Bytewrite for {} {{", input.ident);
+        o += "fn write_to<W>(&self, w: &mut W) -> ::std::io::Result<()>";
+        o += "    where W: ::std::io::Write {";
+        o += &write_body;
         o += "::std::result::Result::Ok(())";
         o += "}}";
 
@@ -40,4 +188,4 @@ pub fn byteparse(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
     // Only structs are valid input for this macro.
     panic!("Invalid input structure");
-}
\ No newline at end of file
+}
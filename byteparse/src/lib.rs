@@ -1,5 +1,13 @@
 pub use byteparse_derive::*;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
+use std::mem::MaybeUninit;
+
+// The derive macro emits fully-qualified `::byteparse::...` paths, which
+// only resolve from outside this crate unless it names itself as one of
+// its own extern preludes. Needed for the `#[derive(Byteparse)]` usage
+// in this crate's own test module below.
+#[cfg(test)]
+extern crate self as byteparse;
 
 /// A type which can be directly created from byte buffer.
 /// Must be primitive, cannot have any padding. Conversion is memcpy-like.
@@ -46,30 +54,73 @@ impl_bc!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize);
 /// Its members must be Byteparse. Conversion is memcpy-like for
 /// primitives and more compilcated for other types. 
 pub unsafe trait Byteparse: Copy {
+    /// Size in bytes of the on-disk representation, i.e. the sum of
+    /// all fields' sizes plus any padding bytes placed between them by
+    /// skip/offset/alignment attributes. Lets callers read a whole instance in one
+    /// `read_exact` instead of one read per field.
+    const SIZE: usize;
+
     /// Parse bytes read from `r` and copy parsed structure to `self`.
     fn parse_to<R: Read>(&mut self, r: &mut R) -> io::Result<()>;
 
     /// Parse bytes read from `r` and return newly parsed structure.
-    fn parse<R: Read>(r: &mut R) -> io::Result<Self>
-        where Self: Default
-    {
-        // Create default instance and parse data into it.
-        let mut s = Self::default();
-        s.parse_to(r)?;
+    fn parse<R: Read>(r: &mut R) -> io::Result<Self> {
+        // Construct the value in place instead of going through
+        // `Default`: large arrays (N > 32) don't implement it, and
+        // this way every `Byteparse` type gets a `parse` for free.
+        let mut s = MaybeUninit::<Self>::uninit();
+
+        unsafe {
+            // Safe because `Self: Copy` means there's no destructor to
+            // run over the not-yet-initialized value, and `parse_to`
+            // fully overwrites it field-by-field before we assume init.
+            (*s.as_mut_ptr()).parse_to(r)?;
+            Ok(s.assume_init())
+        }
+    }
 
-        Ok(s)
+    /// Decode `Self` from a slice holding exactly `SIZE` bytes already
+    /// in memory, without touching any I/O source. Used by callers
+    /// that already did one big `read_exact` up front.
+    fn parse_from_slice(buf: &[u8]) -> Self {
+        let mut cursor = buf;
+        Self::parse(&mut cursor)
+            .expect("slice shorter than Self::SIZE")
     }
 }
 
 // Byteparse for primitives is simple memcpy. Implement Byteparse
 // for all primitives that are Bytecopy.
 unsafe impl<T: Bytecopy> Byteparse for T {
+    const SIZE: usize = std::mem::size_of::<Self>();
+
     fn parse_to<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
         // Just copy the bytes.
         self.copy_to(r)
     }
 }
 
+/// A type which can be written out as a byte buffer, the inverse of
+/// `Byteparse`. Its members must be Bytewrite.
+pub trait Bytewrite {
+    /// Write `self` to `w`.
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()>;
+}
+
+/// Implement Bytewrite trait for primitives.
+macro_rules! impl_bw {
+    ($( $type:tt ),*) => {
+        $( impl Bytewrite for $type {
+            fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+        } )*
+    }
+}
+
+// All primitive types are Bytewrite.
+impl_bw!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize);
+
 /// Helper trait that allows writing
 /// ```reader.parse()```
 /// instead of
@@ -79,11 +130,9 @@ pub trait ByteparseHelper<T, U> {
     fn parse(&mut self) -> io::Result<T>;
 }
 
-// Implement ByteparseHelper for all Byteparse and Default types.
-// Parsed type must be Default because we need to create default instance of it
-// in `Byteparse::parse` function.
+// Implement ByteparseHelper for all Byteparse types.
 impl<T, U> ByteparseHelper<T, U> for U
-    where T: Byteparse + Default, U: Read
+    where T: Byteparse, U: Read
 {
     fn parse(&mut self) -> io::Result<T> {
         // Just call Byteparse implementation.
@@ -91,38 +140,134 @@ impl<T, U> ByteparseHelper<T, U> for U
     }
 }
 
-/// Implement Byteparse for array of `n` Byteparse elements.
-macro_rules! impl_bp_a1 {
-    ($( $n:expr ),*) => {
-        $( unsafe impl<T: Byteparse> Byteparse for [T; $n] {
-            fn parse_to<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
-                // Try to parse each element at once.
-                for v in self.iter_mut() {
-                    v.parse_to(r)?;
-                }
-                
-                Ok(())
-            }
-        } )*
+// Implement Byteparse for arrays of Byteparse types, for any length.
+unsafe impl<T: Byteparse, const N: usize> Byteparse for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn parse_to<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
+        // Try to parse each element at once.
+        for v in self.iter_mut() {
+            v.parse_to(r)?;
+        }
+
+        Ok(())
     }
 }
 
-/// As above, for [n, n + 10) elements.
-macro_rules! impl_bp_a10 {
-    ($( $n:expr ),*) => {
-        $( impl_bp_a1!($n, $n+1, $n+2, $n+3, $n+4,
-            $n+5, $n+6, $n+7, $n+8, $n+9); )*
+// Implement Bytewrite for arrays of Bytewrite types, for any length.
+impl<T: Bytewrite, const N: usize> Bytewrite for [T; N] {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for v in self.iter() {
+            v.write_to(w)?;
+        }
+
+        Ok(())
     }
 }
 
-/// As above, for [n, n + 100) elements.
-macro_rules! impl_bp_a100 {
-    ($( $e:expr ),*) => {
-        $( impl_bp_a10!($e, $e+10, $e+20, $e+30, $e+40,
-            $e+50, $e+60, $e+70, $e+80, $e+90); )*
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Byteparse)]
+    struct WithSkip {
+        a: u32,
+        #[byteparse(skip = 4)]
+        b: u16
+    }
+
+    #[derive(Copy, Clone, Byteparse)]
+    struct WithOffset {
+        a: u8,
+        #[byteparse(offset = 8)]
+        b: u32
     }
-}
 
-// Implement Byteparse for arrays of Byteparse types.
-// Currently size limit is 500 elements.
-impl_bp_a100!(0, 100, 200, 300, 400);
\ No newline at end of file
+    #[derive(Copy, Clone, Byteparse)]
+    #[byteparse(aligned)]
+    struct Aligned {
+        a: u8,
+        b: u32,
+        c: u8,
+        d: u64
+    }
+
+    #[test]
+    fn skip_pads_size_and_write_with_zeroes() {
+        // a:u32 (4B) -> 4B of skipped padding -> b:u16 (2B) -> 10.
+        assert_eq!(WithSkip::SIZE, 4 + 4 + 2);
+
+        let val = WithSkip { a: 0xAABBCCDD, b: 0x1234 };
+
+        let mut buf = Vec::new();
+        val.write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), WithSkip::SIZE);
+        assert_eq!(&buf[4..8], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn skip_round_trips() {
+        let val = WithSkip { a: 0xAABBCCDD, b: 0x1234 };
+
+        let mut buf = Vec::new();
+        val.write_to(&mut buf).unwrap();
+
+        let mut cursor = &buf[..];
+        let parsed = WithSkip::parse(&mut cursor).unwrap();
+
+        assert_eq!(parsed.a, val.a);
+        assert_eq!(parsed.b, val.b);
+    }
+
+    #[test]
+    fn offset_pads_size_and_write_with_zeroes() {
+        // a:u8 @0 (1B) -> padded up to offset 8 -> b:u32 (4B) -> 12.
+        assert_eq!(WithOffset::SIZE, 8 + 4);
+
+        let val = WithOffset { a: 0xAB, b: 0xDEADBEEF };
+
+        let mut buf = Vec::new();
+        val.write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), WithOffset::SIZE);
+        assert_eq!(buf[0], 0xAB);
+        assert_eq!(&buf[1..8], &[0; 7]);
+    }
+
+    #[test]
+    fn offset_round_trips() {
+        let val = WithOffset { a: 0xAB, b: 0xDEADBEEF };
+
+        let mut buf = Vec::new();
+        val.write_to(&mut buf).unwrap();
+
+        let mut cursor = &buf[..];
+        let parsed = WithOffset::parse(&mut cursor).unwrap();
+
+        assert_eq!(parsed.a, val.a);
+        assert_eq!(parsed.b, val.b);
+    }
+
+    #[test]
+    fn aligned_struct_pads_between_fields() {
+        // a:u8 @0 (1B) -> b:u32 needs 3B pad to reach @4 (4B) ->
+        // c:u8 @8 (1B) -> d:u64 needs 7B pad to reach @16 (8B) -> 24.
+        assert_eq!(Aligned::SIZE, 24);
+    }
+
+    #[test]
+    fn aligned_struct_round_trips() {
+        let val = Aligned { a: 1, b: 2, c: 3, d: 4 };
+
+        let mut buf = Vec::new();
+        val.write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), Aligned::SIZE);
+
+        let mut cursor = &buf[..];
+        let parsed = Aligned::parse(&mut cursor).unwrap();
+
+        assert_eq!(parsed.a, 1);
+        assert_eq!(parsed.b, 2);
+        assert_eq!(parsed.c, 3);
+        assert_eq!(parsed.d, 4);
+    }
+}
\ No newline at end of file
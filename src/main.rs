@@ -5,12 +5,16 @@ use std::mem;
 
 mod pe;
 
+/// Default minimum length (in bytes, or UTF-16 units) for a printable
+/// run to be reported by `strings()`/`load_strings`.
+const DEFAULT_MIN_STRING_LEN: usize = 4;
+
 pub trait PeSource: BufRead + Seek {}
 impl<T: BufRead + Seek> PeSource for T {}
 
 pub enum ExportAddr {
     Rva(u64),
-    Forwarded((String, String))
+    Forwarded(u64, String, String)
 }
 
 pub enum ImportFunc {
@@ -24,9 +28,14 @@ pub struct Export {
     pub ord:  u16
 }
 
+pub struct ImportThunk {
+    pub func: ImportFunc,
+    pub rva:  u64
+}
+
 pub struct Import {
     pub name:  String,
-    pub funcs: Vec<ImportFunc>
+    pub funcs: Vec<ImportThunk>
 }
 
 pub struct Section {
@@ -38,12 +47,31 @@ pub struct Section {
     pub flags:     u32
 }
 
+pub struct Reloc {
+    pub rva:  u64,
+    pub kind: u16
+}
+
+pub enum StringEncoding {
+    Ascii,
+    Utf16
+}
+
+pub struct FoundString {
+    pub rva:      u64,
+    pub encoding: StringEncoding,
+    pub text:     String
+}
+
 pub struct PortableExecutable {
     dos:     pe::ImageDosHeader,
     nt:      pe::ImageNtHeaders64,
     secs:    Vec<Section>,
     exports: Vec<Export>,
-    imports: Vec<Import>
+    imports: Vec<Import>,
+    relocs:  Vec<Reloc>,
+    strings: Vec<FoundString>,
+    raw:     Vec<u8>
 }
 
 impl PortableExecutable {
@@ -55,6 +83,14 @@ impl PortableExecutable {
         &self.imports
     }
 
+    pub fn relocations(&self) -> &[Reloc] {
+        &self.relocs
+    }
+
+    pub fn strings(&self) -> &[FoundString] {
+        &self.strings
+    }
+
     pub fn exports(&self) -> &[Export] {
         &self.exports
     }
@@ -70,10 +106,80 @@ impl PortableExecutable {
     pub fn dos_header(&self) -> &pe::ImageDosHeader {
         &self.dos
     }
+
+    pub fn entry_point(&self) -> u64 {
+        self.nt.optional_header.address_of_entry_point as u64
+    }
+
+    /// Read `len` raw bytes starting at `rva`. Built for disassembler
+    /// integration: decode loops can pull a function's code out of
+    /// the image without having to seek the original source.
+    pub fn read_rva(&self, rva: u64, len: usize) -> io::Result<Vec<u8>> {
+        let off = self.conv_rva(rva) as usize;
+
+        self.raw.get(off..off + len)
+            .map(|s| s.to_vec())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof,
+                "RVA range falls outside of the image"))
+    }
+
+    /// For each `Rva` export, the code bytes from its address up to
+    /// the next export (by address) or the end of its section,
+    /// whichever comes first. Mirrors a linear-sweep disassembler's
+    /// per-function byte window, without prescribing a decoder.
+    pub fn export_bodies(&self)
+        -> impl Iterator<Item = (&Export, io::Result<Vec<u8>>)> + '_
+    {
+        let mut rvas: Vec<u64> = self.exports.iter()
+            .filter_map(|e| match e.addr {
+                ExportAddr::Rva(rva) => Some(rva),
+                _ => None
+            })
+            .collect();
+        rvas.sort_unstable();
+
+        self.exports.iter().filter_map(move |e| {
+            let rva = match e.addr {
+                ExportAddr::Rva(rva) => rva,
+                _ => return None
+            };
+
+            let section_end = self.secs.iter()
+                .find(|s| rva >= s.virt_addr as u64 &&
+                    rva < (s.virt_addr + s.virt_len) as u64)
+                .map(|s| (s.virt_addr + s.virt_len) as u64)
+                .unwrap_or(rva);
+
+            let next_export = rvas.iter().copied()
+                .find(|&r| r > rva)
+                .unwrap_or(u64::MAX);
+
+            let end = section_end.min(next_export);
+            let len = (end - rva) as usize;
+
+            Some((e, self.read_rva(rva, len)))
+        })
+    }
+
+    fn conv_rva(&self, rva: u64) -> u64 {
+        for sec in &self.secs {
+            if rva >= sec.virt_addr as u64 &&
+                rva < (sec.virt_addr + sec.virt_len) as u64
+            {
+                return rva - sec.virt_addr as u64 + sec.raw_addr as u64;
+            }
+        }
+
+        rva
+    }
 }
 
 struct PortableExecutableParser<T: PeSource> {
     source: RefCell<T>,
+    // Reused across every `at` call so parsing many fixed-size
+    // records (section/import/relocation tables) doesn't allocate
+    // a fresh buffer per record.
+    buf:    RefCell<Vec<u8>>,
     p:      PortableExecutable
 }
 
@@ -84,11 +190,15 @@ impl<T: PeSource> PortableExecutableParser<T> {
             nt:      Default::default(),
             secs:    Default::default(),
             exports: Default::default(),
-            imports: Default::default()
+            imports: Default::default(),
+            relocs:  Default::default(),
+            strings: Default::default(),
+            raw:     Default::default()
         };
 
         let mut parser = Self {
             source: RefCell::new(source),
+            buf:    RefCell::new(Vec::new()),
             p
         };
 
@@ -97,6 +207,16 @@ impl<T: PeSource> PortableExecutableParser<T> {
     }
 
     fn parse_self(&mut self) -> io::Result<()> {
+        // Keep a copy of the whole image around so `read_rva` can
+        // serve byte ranges after parsing without needing the
+        // original source to stay alive or seekable.
+        {
+            let mut source = self.source.borrow_mut();
+            source.seek(std::io::SeekFrom::Start(0))?;
+            source.read_to_end(&mut self.p.raw)?;
+            source.seek(std::io::SeekFrom::Start(0))?;
+        }
+
         self.p.dos = self.at(0)?;
         self.p.nt  = self.at(self.p.dos.e_lfanew as u64)?;
 
@@ -106,6 +226,8 @@ impl<T: PeSource> PortableExecutableParser<T> {
         self.load_sections()?;
         self.load_exports()?;
         self.load_imports()?;
+        self.load_relocs()?;
+        self.load_strings(DEFAULT_MIN_STRING_LEN)?;
         Ok(())
     }
 
@@ -203,10 +325,10 @@ impl<T: PeSource> PortableExecutableParser<T> {
                 let mut iter  = forwarder.splitn(2, '.');
 
                 if let (Some(m), Some(f)) = (iter.next(), iter.next()) {
-                    ExportAddr::Forwarded((m.to_owned() + ".dll",
-                        f.to_owned()))
+                    ExportAddr::Forwarded(func, m.to_owned() + ".dll",
+                        f.to_owned())
                 } else {
-                    ExportAddr::Forwarded((forwarder, "".to_owned()))
+                    ExportAddr::Forwarded(func, forwarder, "".to_owned())
                 }
 
             } else {
@@ -250,6 +372,7 @@ impl<T: PeSource> PortableExecutableParser<T> {
             } else {
                 import.original_first_thunk as u64
             };
+            let mut iat_addr = import.first_thunk as u64;
 
             loop {
                 let entry: u64 = self.at_rva(lookup_addr)?;
@@ -258,11 +381,14 @@ impl<T: PeSource> PortableExecutableParser<T> {
                 }
                 lookup_addr += 8;
 
-                module.funcs.push(if entry & 0x8000000000000000 != 0 {
+                let func = if entry & 0x8000000000000000 != 0 {
                     ImportFunc::ByOrd(entry as u16)
                 } else {
                     ImportFunc::ByName(self.read_str(entry + 2)?)
-                });
+                };
+
+                module.funcs.push(ImportThunk { func, rva: iat_addr });
+                iat_addr += 8;
             }
 
             self.p.imports.push(module);
@@ -271,16 +397,143 @@ impl<T: PeSource> PortableExecutableParser<T> {
         Ok(())
     }
 
-    fn conv_rva(&self, rva: u64) -> u64 {
-        for sec in &self.p.secs {
-            if rva >= sec.virt_addr as u64 && 
-                rva < (sec.virt_addr + sec.virt_len) as u64 
-            {
-                return rva - sec.virt_addr as u64 + sec.raw_addr as u64;
+    fn load_relocs(&mut self) -> io::Result<()> {
+        const IMAGE_REL_BASED_ABSOLUTE: u16 = 0;
+
+        let dir     = self.p.nt.optional_header.data_directory[5];
+        let dir_rva = dir.virtual_address as u64;
+        let dir_len = dir.size            as u64;
+
+        if dir_rva == 0 || dir_len == 0 {
+            return Ok(());
+        }
+
+        let mut block_addr = dir_rva;
+        let mut consumed   = 0u64;
+
+        while consumed < dir_len {
+            let page_rva:   u32 = self.at_rva(block_addr)?;
+            let block_size: u32 = self.at_rva(block_addr + 4)?;
+
+            // A trailing zero-size block is used as padding; bail out
+            // instead of underflowing the entry count below.
+            if block_size < 8 {
+                break;
+            }
+
+            let entry_count = (block_size as u64 - 8) / 2;
+            for i in 0..entry_count {
+                let entry: u16 = self.at_rva(block_addr + 8 + i * 2)?;
+                let kind       = entry >> 12;
+                let offset     = entry & 0xFFF;
+
+                if kind == IMAGE_REL_BASED_ABSOLUTE {
+                    continue;
+                }
+
+                self.p.relocs.push(Reloc {
+                    rva: page_rva as u64 + offset as u64,
+                    kind
+                });
             }
+
+            consumed   += block_size as u64;
+            block_addr += block_size as u64;
         }
 
-        rva
+        Ok(())
+    }
+
+    /// Find embedded strings, keeping only printable runs of at least
+    /// `min_len` bytes/UTF-16 units.
+    fn load_strings(&mut self, min_len: usize) -> io::Result<()> {
+        const IMAGE_SCN_CNT_CODE: u32 = 0x00000020;
+        const IMAGE_SCN_MEM_READ: u32 = 0x40000000;
+
+        fn is_printable(b: u8) -> bool {
+            matches!(b, 0x20..=0x7E | b'\t' | b'\n')
+        }
+
+        // Scope this to readable sections that don't hold code, not
+        // every section in the image: scanning `.text` (and friends) as
+        // if it were data floods the result with false positives pulled
+        // out of executable code. Other readable data sections (`.reloc`,
+        // `.pdata`, `.rsrc`, ...) are still scanned.
+        let sections: Vec<(u64, u64, usize)> = self.p.secs.iter()
+            .filter(|sec| sec.flags & IMAGE_SCN_MEM_READ != 0
+                && sec.flags & IMAGE_SCN_CNT_CODE == 0)
+            .map(|sec| (sec.virt_addr as u64, sec.raw_addr as u64,
+                sec.raw_len as usize))
+            .collect();
+
+        for (virt_addr, raw_addr, raw_len) in sections {
+            if raw_len == 0 {
+                continue;
+            }
+
+            // The whole image is already buffered in `self.p.raw`
+            // (see parse_self), so slice it directly instead of
+            // re-reading each section's bytes from the source. The
+            // section table is attacker-controllable, so bounds-check
+            // rather than indexing blindly (as `read_rva` does).
+            let raw_addr = raw_addr as usize;
+            let data = match self.p.raw.get(raw_addr..raw_addr + raw_len) {
+                Some(data) => data,
+                None       => continue
+            };
+
+            let mut offset = 0;
+            while offset < data.len() {
+                if !is_printable(data[offset]) {
+                    offset += 1;
+                    continue;
+                }
+
+                // UTF-16LE runs show up as a printable byte followed
+                // by a zero byte, repeating.
+                if offset + 1 < data.len() && data[offset + 1] == 0 {
+                    let start = offset;
+                    let mut units = Vec::new();
+
+                    while offset + 1 < data.len()
+                        && is_printable(data[offset]) && data[offset + 1] == 0
+                    {
+                        units.push(data[offset] as u16);
+                        offset += 2;
+                    }
+
+                    if units.len() >= min_len {
+                        self.p.strings.push(FoundString {
+                            rva:      virt_addr + start as u64,
+                            encoding: StringEncoding::Utf16,
+                            text:     String::from_utf16_lossy(&units)
+                        });
+                    }
+
+                    continue;
+                }
+
+                let start = offset;
+                while offset < data.len() && is_printable(data[offset]) {
+                    offset += 1;
+                }
+
+                if offset - start >= min_len {
+                    self.p.strings.push(FoundString {
+                        rva:      virt_addr + start as u64,
+                        encoding: StringEncoding::Ascii,
+                        text: String::from_utf8_lossy(&data[start..offset])
+                            .to_string()
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn conv_rva(&self, rva: u64) -> u64 {
+        self.p.conv_rva(rva)
     }
 
     fn read_str(&self, rva: u64) -> io::Result<String> {
@@ -297,18 +550,27 @@ impl<T: PeSource> PortableExecutableParser<T> {
     }
 
     fn at_rva<U>(&self, rva: u64) -> io::Result<U>
-        where U: Byteparse + Default
+        where U: Byteparse
     {
         self.at(self.conv_rva(rva))
     }
 
     fn at<U>(&self, off: u64) -> io::Result<U>
-        where U: Byteparse + Default
+        where U: Byteparse
     {
         let mut source = self.source.borrow_mut();
+        let mut buf     = self.buf.borrow_mut();
+
+        // Read the whole struct in one go instead of one read per
+        // field, then decode it from the in-memory slice.
+        if buf.len() < U::SIZE {
+            buf.resize(U::SIZE, 0);
+        }
 
         source.seek(std::io::SeekFrom::Start(off))?;
-        source.parse()
+        source.read_exact(&mut buf[..U::SIZE])?;
+
+        Ok(U::parse_from_slice(&buf[..U::SIZE]))
     }
 }
 
@@ -318,9 +580,9 @@ fn dump_imports(pe: &PortableExecutable, w: &mut impl Write)
     for module in pe.imports() {
         writeln!(w, "{} {{", module.name)?;
             
-        for func in &module.funcs {
+        for thunk in &module.funcs {
             write!(w, "    ")?;
-            match func {
+            match &thunk.func {
                 ImportFunc::ByName(name) => writeln!(w, "{},",   name)?,
                 ImportFunc::ByOrd(ord)   => writeln!(w, "[{}],", ord)?,
             }
@@ -344,7 +606,7 @@ fn dump_exports(pe: &PortableExecutable, w: &mut impl Write)
         match &export.addr {
             ExportAddr::Rva(rva) => 
                 write!(w, "{:016X}", rva)?,
-            ExportAddr::Forwarded((m, f)) => 
+            ExportAddr::Forwarded(_, m, f) =>
                 write!(w, "[{}: {}]", m, f)?
         }
 
@@ -354,7 +616,85 @@ fn dump_exports(pe: &PortableExecutable, w: &mut impl Write)
     Ok(())
 }
 
-fn dump_sections(pe: &PortableExecutable, w: &mut impl Write) 
+fn dump_relocs(pe: &PortableExecutable, w: &mut impl Write)
+    -> io::Result<()>
+{
+    for reloc in pe.relocations() {
+        writeln!(w, "{:016X} [{}]", reloc.rva, reloc.kind)?;
+    }
+
+    Ok(())
+}
+
+fn dump_strings(pe: &PortableExecutable, w: &mut impl Write)
+    -> io::Result<()>
+{
+    for s in pe.strings() {
+        let enc = match s.encoding {
+            StringEncoding::Ascii => "ascii",
+            StringEncoding::Utf16 => "utf16"
+        };
+
+        writeln!(w, "{:016X} [{}] {}", s.rva, enc, s.text)?;
+    }
+
+    Ok(())
+}
+
+fn section_for_rva(pe: &PortableExecutable, rva: u64) -> &str {
+    for sec in pe.sections() {
+        if rva >= sec.virt_addr as u64 &&
+            rva < (sec.virt_addr + sec.virt_len) as u64
+        {
+            return &sec.name;
+        }
+    }
+
+    "?"
+}
+
+fn dump_map(pe: &PortableExecutable, w: &mut impl Write)
+    -> io::Result<()>
+{
+    let mut entries: Vec<(u64, String)> = Vec::new();
+
+    for export in pe.exports() {
+        let name = export.name.clone()
+            .unwrap_or_else(|| format!("ord_{}", export.ord));
+
+        match &export.addr {
+            ExportAddr::Rva(rva) =>
+                entries.push((*rva, name)),
+            ExportAddr::Forwarded(rva, m, f) =>
+                entries.push((*rva, format!("{}!{}", m, f)))
+        }
+    }
+
+    for module in pe.imports() {
+        for thunk in &module.funcs {
+            let name = match &thunk.func {
+                ImportFunc::ByName(n) => format!("{}!{}", module.name, n),
+                ImportFunc::ByOrd(o)  => format!("{}!#{}", module.name, o)
+            };
+
+            entries.push((thunk.rva, name));
+        }
+    }
+
+    for section in pe.sections() {
+        entries.push((section.virt_addr as u64, "<section start>".to_string()));
+    }
+
+    entries.sort_by_key(|(rva, _)| *rva);
+
+    for (rva, name) in &entries {
+        writeln!(w, "{:016X} {: <8} {}", rva, section_for_rva(pe, *rva), name)?;
+    }
+
+    Ok(())
+}
+
+fn dump_sections(pe: &PortableExecutable, w: &mut impl Write)
     -> io::Result<()>
 {
     for section in pe.sections() {
@@ -388,10 +728,13 @@ fn main() -> io::Result<()> {
     let cur = std::io::Cursor::new(&buf[..]);
     let pe  = Arc::new(PortableExecutable::parse(cur)?);
 
-    const FILE_NAMES: [&str; 3] = [
+    const FILE_NAMES: [&str; 6] = [
         "sections.txt",
         "imports.txt",
-        "exports.txt"
+        "exports.txt",
+        "relocs.txt",
+        "strings.txt",
+        "map.txt"
     ];
 
     let mut threads = Vec::new();
@@ -406,6 +749,9 @@ fn main() -> io::Result<()> {
                 0 => dump_sections(&pe, &mut f),
                 1 => dump_imports(&pe,  &mut f),
                 2 => dump_exports(&pe,  &mut f),
+                3 => dump_relocs(&pe,   &mut f),
+                4 => dump_strings(&pe,  &mut f),
+                5 => dump_map(&pe,      &mut f),
                 _ => panic!()
             }
         }));